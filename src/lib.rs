@@ -1,7 +1,9 @@
 #[macro_use]
 extern crate ndarray;
 use ndarray::prelude::*;
+use std::convert::TryFrom;
 use std::f32;
+use std::fmt;
 use std::ops;
 
 #[derive(Clone, PartialEq, Debug)]
@@ -41,6 +43,32 @@ impl Quaternion {
         Self::new(array![0.0, v[0], v[1], v[2]])
     }
 
+    /// Builds a unit quaternion representing a rotation of `angle` radians
+    /// around `axis`. `axis` need not be normalized.
+    pub fn from_axis_angle(axis: &Array1<f32>, angle: f32) -> Self {
+        assert_eq!(axis.len(), 3);
+        let axis = axis / axis.dot(axis).sqrt();
+        let s = (angle / 2.0).sin();
+        Self::new(array![
+            (angle / 2.0).cos(),
+            s * axis[0],
+            s * axis[1],
+            s * axis[2],
+        ])
+    }
+
+    /// Decomposes a unit quaternion into an (axis, angle) pair such that
+    /// `Quaternion::from_axis_angle(&axis, angle)` recovers an equivalent
+    /// rotation.
+    pub fn to_axis_angle(&self) -> (Array1<f32>, f32) {
+        let angle = 2.0 * self.q[0].acos();
+        let denom = (1.0 - self.q[0] * self.q[0]).sqrt();
+        if denom < f32::EPSILON {
+            return (array![1.0, 0.0, 0.0], 0.0);
+        }
+        (self.vector() / denom, angle)
+    }
+
     pub fn scalar(&self) -> f32 {
         self.q[0]
     }
@@ -120,21 +148,296 @@ impl Quaternion {
         rotated.to_vector()
     }
 
+    /// Builds a quaternion from Tait-Bryan angles (ZYX convention), inverting
+    /// `taitbryan`.
+    pub fn from_euler(yaw: f32, pitch: f32, roll: f32) -> Self {
+        let (cy, sy) = ((yaw * 0.5).cos(), (yaw * 0.5).sin());
+        let (cp, sp) = ((pitch * 0.5).cos(), (pitch * 0.5).sin());
+        let (cr, sr) = ((roll * 0.5).cos(), (roll * 0.5).sin());
+
+        Self::from_wxyz(
+            cr * cp * cy + sr * sp * sy,
+            sr * cp * cy - cr * sp * sy,
+            cr * sp * cy + sr * cp * sy,
+            cr * cp * sy - sr * sp * cy,
+        )
+    }
+
     /// Returns [yaw, pitch, roll]
     pub fn taitbryan(&self) -> Array1<f32> {
         let q = &self.q;
-        let tb1 = 2.0 * (q[0] * q[2] - q[1] * q[3]).asin();
+        let tb1 = (2.0 * (q[0] * q[2] - q[1] * q[3])).asin();
         let tb0 =
-            (2.0 * (q[2] * q[3] + q[0] * q[1])).atan2(1.0 - 2.0 * (q[1] * q[1] + q[2] * q[2]));
-        let tb2 =
             (2.0 * (q[1] * q[2] + q[0] * q[3])).atan2(1.0 - 2.0 * (q[2] * q[2] + q[3] * q[3]));
+        let tb2 =
+            (2.0 * (q[2] * q[3] + q[0] * q[1])).atan2(1.0 - 2.0 * (q[1] * q[1] + q[2] * q[2]));
         array![tb0, tb1, tb2]
     }
+
+    /// Expands a unit quaternion into the equivalent 3x3 rotation matrix.
+    pub fn to_rotation_matrix(&self) -> Array2<f32> {
+        let q = &self.q;
+        let (w, x, y, z) = (q[0], q[1], q[2], q[3]);
+        array![
+            [
+                1.0 - 2.0 * (y * y + z * z),
+                2.0 * (x * y - w * z),
+                2.0 * (x * z + w * y),
+            ],
+            [
+                2.0 * (x * y + w * z),
+                1.0 - 2.0 * (x * x + z * z),
+                2.0 * (y * z - w * x),
+            ],
+            [
+                2.0 * (x * z - w * y),
+                2.0 * (y * z + w * x),
+                1.0 - 2.0 * (x * x + y * y),
+            ],
+        ]
+    }
+
+    /// Expands a unit quaternion into the equivalent 4x4 homogeneous
+    /// rotation matrix.
+    pub fn to_homogeneous(&self) -> Array2<f32> {
+        let r = self.to_rotation_matrix();
+        let mut m = Array2::eye(4);
+        m.slice_mut(s![0..3, 0..3]).assign(&r);
+        m
+    }
+
+    /// Builds a quaternion from a 3x3 rotation matrix, using the
+    /// trace-based (Shepperd) method for numerical stability.
+    pub fn from_rotation_matrix(m: &Array2<f32>) -> Self {
+        assert_eq!(m.shape(), &[3, 3]);
+        let trace = m[[0, 0]] + m[[1, 1]] + m[[2, 2]];
+        if trace > 0.0 {
+            let s = 0.5 / (trace + 1.0).sqrt();
+            Self::from_wxyz(
+                0.25 / s,
+                (m[[2, 1]] - m[[1, 2]]) * s,
+                (m[[0, 2]] - m[[2, 0]]) * s,
+                (m[[1, 0]] - m[[0, 1]]) * s,
+            )
+        } else if m[[0, 0]] > m[[1, 1]] && m[[0, 0]] > m[[2, 2]] {
+            let s = 2.0 * (1.0 + m[[0, 0]] - m[[1, 1]] - m[[2, 2]]).sqrt();
+            Self::from_wxyz(
+                (m[[2, 1]] - m[[1, 2]]) / s,
+                0.25 * s,
+                (m[[0, 1]] + m[[1, 0]]) / s,
+                (m[[0, 2]] + m[[2, 0]]) / s,
+            )
+        } else if m[[1, 1]] > m[[2, 2]] {
+            let s = 2.0 * (1.0 + m[[1, 1]] - m[[0, 0]] - m[[2, 2]]).sqrt();
+            Self::from_wxyz(
+                (m[[0, 2]] - m[[2, 0]]) / s,
+                (m[[0, 1]] + m[[1, 0]]) / s,
+                0.25 * s,
+                (m[[1, 2]] + m[[2, 1]]) / s,
+            )
+        } else {
+            let s = 2.0 * (1.0 + m[[2, 2]] - m[[0, 0]] - m[[1, 1]]).sqrt();
+            Self::from_wxyz(
+                (m[[1, 0]] - m[[0, 1]]) / s,
+                (m[[0, 2]] + m[[2, 0]]) / s,
+                (m[[1, 2]] + m[[2, 1]]) / s,
+                0.25 * s,
+            )
+        }
+    }
+
+    /// Dot product of the underlying 4-vectors.
+    pub fn dot(&self, other: &Quaternion) -> f32 {
+        self.q.dot(&other.q)
+    }
+
+    /// Normalized linear interpolation between `self` and `other`.
+    pub fn nlerp(&self, other: &Quaternion, t: f32) -> Self {
+        let interpolated = Self::new(&self.q * (1.0 - t) + &other.q * t);
+        interpolated.normalized()
+    }
+
+    /// Spherical linear interpolation between `self` and `other`, assuming both
+    /// are (or are close to) unit quaternions. Takes the shorter arc between
+    /// the two orientations.
+    pub fn slerp(&self, other: &Quaternion, t: f32) -> Self {
+        let mut dot = self.dot(other);
+        let other = if dot < 0.0 {
+            dot = -dot;
+            Self::new(-&other.q)
+        } else {
+            other.clone()
+        };
+
+        if dot > 0.9995 {
+            return self.nlerp(&other, t);
+        }
+
+        let theta_0 = dot.acos();
+        let theta = theta_0 * t;
+        let q2 = Self::new(&other.q - &(&self.q * dot)).normalized();
+
+        Self::new(&self.q * theta.cos() + &q2.q * theta.sin())
+    }
+
+    /// Exponential of a quaternion, `e^q`.
+    pub fn exp(&self) -> Self {
+        let w = self.scalar();
+        let v = self.vector();
+        let nv = v.dot(&v).sqrt();
+        let ew = w.exp();
+
+        if nv < f32::EPSILON {
+            return Self::from_wxyz(ew, 0.0, 0.0, 0.0);
+        }
+
+        let coeff = ew * nv.sin() / nv;
+        Self::new(array![ew * nv.cos(), coeff * v[0], coeff * v[1], coeff * v[2]])
+    }
+
+    /// Natural logarithm of a nonzero quaternion.
+    pub fn ln(&self) -> Self {
+        let w = self.scalar();
+        let v = self.vector();
+        let n = self.norm();
+        let nv = v.dot(&v).sqrt();
+
+        if nv < f32::EPSILON {
+            return Self::from_wxyz(n.ln(), 0.0, 0.0, 0.0);
+        }
+
+        let coeff = (w / n).acos() / nv;
+        Self::new(array![n.ln(), coeff * v[0], coeff * v[1], coeff * v[2]])
+    }
+
+    /// Raises a quaternion to a real power, e.g. for fractional rotations.
+    pub fn powf(&self, n: f32) -> Self {
+        Self::new(self.ln().q * n).exp()
+    }
+}
+
+/// Error returned when constructing a [`UnitQuaternion`] from a
+/// [`Quaternion`] that is not of unit length.
+#[derive(Clone, PartialEq, Debug)]
+pub struct NotUnitError;
+
+impl fmt::Display for NotUnitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "quaternion is not of unit length")
+    }
+}
+
+impl std::error::Error for NotUnitError {}
+
+/// A `Quaternion` known to be of unit length, so rotation-only operations can
+/// skip re-checking `is_unit` and use cheaper unit-length formulas (e.g.
+/// `inverse` as a plain conjugate).
+#[derive(Clone, PartialEq, Debug)]
+pub struct UnitQuaternion(Quaternion);
+
+impl UnitQuaternion {
+    /// Normalizes `q` and wraps it as a `UnitQuaternion`.
+    pub fn new_normalize(q: Quaternion) -> Self {
+        UnitQuaternion(q.normalized())
+    }
+
+    pub fn rotate_vector(&self, v: &Array1<f32>) -> Array1<f32> {
+        self.0.rotate_vector(v)
+    }
+
+    pub fn slerp(&self, other: &UnitQuaternion, t: f32) -> Self {
+        UnitQuaternion(self.0.slerp(&other.0, t))
+    }
+
+    pub fn to_rotation_matrix(&self) -> Array2<f32> {
+        self.0.to_rotation_matrix()
+    }
+
+    /// Returns [yaw, pitch, roll]
+    pub fn taitbryan(&self) -> Array1<f32> {
+        self.0.taitbryan()
+    }
+
+    /// Inverse of a unit quaternion is just its conjugate, avoiding the
+    /// `sum_of_squares` division `Quaternion::inverse` needs in general.
+    pub fn inverse(&self) -> Self {
+        UnitQuaternion(self.0.conjugate())
+    }
+}
+
+impl From<UnitQuaternion> for Quaternion {
+    fn from(q: UnitQuaternion) -> Self {
+        q.0
+    }
+}
+
+/// Looser than `Quaternion::is_unit`'s `f32::EPSILON`, which `normalize()`
+/// itself cannot always achieve (e.g. normalizing `[1.0, 0.5, 0.5, 0.5]`
+/// leaves a norm error bigger than `f32::EPSILON`).
+const UNIT_TOLERANCE: f32 = 0.00001;
+
+impl TryFrom<Quaternion> for UnitQuaternion {
+    type Error = NotUnitError;
+
+    fn try_from(q: Quaternion) -> Result<Self, Self::Error> {
+        if (1.0 - q.norm()).abs() < UNIT_TOLERANCE {
+            Ok(UnitQuaternion(q))
+        } else {
+            Err(NotUnitError)
+        }
+    }
+}
+
+/// Hamilton product of `N` quaternion pairs, given as `(N, 4)` arrays, computed
+/// with ndarray broadcasting instead of building a `q_matrix` per row.
+pub fn hamilton_product_batch(a: &ArrayView2<f32>, b: &ArrayView2<f32>) -> Array2<f32> {
+    assert_eq!(a.shape()[1], 4);
+    assert_eq!(b.shape()[1], 4);
+    assert_eq!(a.shape()[0], b.shape()[0]);
+
+    let (w1, x1, y1, z1) = (a.column(0), a.column(1), a.column(2), a.column(3));
+    let (w2, x2, y2, z2) = (b.column(0), b.column(1), b.column(2), b.column(3));
+
+    let w = &w1 * &w2 - &x1 * &x2 - &y1 * &y2 - &z1 * &z2;
+    let x = &w1 * &x2 + &x1 * &w2 + &y1 * &z2 - &z1 * &y2;
+    let y = &w1 * &y2 - &x1 * &z2 + &y1 * &w2 + &z1 * &x2;
+    let z = &w1 * &z2 + &x1 * &y2 - &y1 * &x2 + &z1 * &w2;
+
+    let mut result = Array2::zeros((a.shape()[0], 4));
+    result.column_mut(0).assign(&w);
+    result.column_mut(1).assign(&x);
+    result.column_mut(2).assign(&y);
+    result.column_mut(3).assign(&z);
+    result
+}
+
+/// Rotates `N` vectors, given as an `(N, 3)` array, each by its corresponding
+/// quaternion in an `(N, 4)` array, returning the rotated vectors as `(N, 3)`.
+pub fn rotate_vectors(quats: &ArrayView2<f32>, vecs: &ArrayView2<f32>) -> Array2<f32> {
+    assert_eq!(quats.shape()[1], 4);
+    assert_eq!(vecs.shape()[1], 3);
+    assert_eq!(quats.shape()[0], vecs.shape()[0]);
+
+    let n = vecs.shape()[0];
+    let mut pure_quats = Array2::zeros((n, 4));
+    pure_quats.slice_mut(s![.., 1..4]).assign(vecs);
+
+    let mut conjugates = quats.to_owned();
+    conjugates.slice_mut(s![.., 1..4]).mapv_inplace(|v| -v);
+
+    let rotated = hamilton_product_batch(
+        &hamilton_product_batch(quats, &pure_quats.view()).view(),
+        &conjugates.view(),
+    );
+    rotated.slice(s![.., 1..4]).to_owned()
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Quaternion;
+    use super::{hamilton_product_batch, rotate_vectors, Quaternion, UnitQuaternion};
+    use ndarray::Array2;
+    use std::convert::TryFrom;
+    use std::f32;
 
     #[test]
     fn is_unit() {
@@ -215,4 +518,182 @@ mod tests {
         let v2 = q.rotate_vector(&v);
         assert_eq!(v2, array![0.18197358, 0.8871603, 9.521111])
     }
+
+    #[test]
+    fn dot() {
+        let q1 = Quaternion::new(array![1.0, 0.0, 0.0, 0.0]);
+        let q2 = Quaternion::new(array![0.0, 1.0, 0.0, 0.0]);
+        assert_eq!(q1.dot(&q2), 0.0);
+        assert_eq!(q1.dot(&q1), 1.0);
+    }
+
+    #[test]
+    fn slerp_endpoints() {
+        let q1 = Quaternion::from_wxyz(1.0, 0.0, 0.0, 0.0);
+        let q2 = Quaternion::new(array![0.5, 0.5, 0.5, 0.5]);
+        assert_eq!(q1.slerp(&q2, 0.0).q, q1.q);
+        // t=1.0 is checked component-wise rather than with assert_eq!: the
+        // result is rebuilt as self*cos(theta_0) + q2*sin(theta_0) from
+        // theta_0 = acos(dot) and a normalized q2, so it picks up f32
+        // rounding that an exact comparison doesn't tolerate.
+        let end = q1.slerp(&q2, 1.0);
+        for i in 0..4 {
+            assert!((end.q[i] - q2.q[i]).abs() < 0.0001);
+        }
+    }
+
+    #[test]
+    fn slerp_midpoint_is_unit() {
+        let q1 = Quaternion::from_wxyz(1.0, 0.0, 0.0, 0.0);
+        let q2 = Quaternion::new(array![0.5, 0.5, 0.5, 0.5]);
+        let mid = q1.slerp(&q2, 0.5);
+        assert!((1.0 - mid.norm()).abs() < 0.0001);
+    }
+
+    #[test]
+    fn axis_angle_round_trip() {
+        let axis = array![0.0, 1.0, 0.0];
+        let angle = f32::consts::FRAC_PI_2;
+        let q = Quaternion::from_axis_angle(&axis, angle);
+        let (axis2, angle2) = q.to_axis_angle();
+        assert!((axis2[0] - axis[0]).abs() < 0.0001);
+        assert!((axis2[1] - axis[1]).abs() < 0.0001);
+        assert!((axis2[2] - axis[2]).abs() < 0.0001);
+        assert!((angle2 - angle).abs() < 0.0001);
+    }
+
+    #[test]
+    fn axis_angle_identity() {
+        let q = Quaternion::from_wxyz(1.0, 0.0, 0.0, 0.0);
+        let (axis, angle) = q.to_axis_angle();
+        assert_eq!(axis, array![1.0, 0.0, 0.0]);
+        assert_eq!(angle, 0.0);
+    }
+
+    #[test]
+    fn unit_quaternion_new_normalize() {
+        let q = Quaternion::new(array![1.0, 0.5, 0.5, 0.5]);
+        let uq = UnitQuaternion::new_normalize(q);
+        let q: Quaternion = uq.into();
+        assert!((1.0 - q.norm()).abs() < 0.0001);
+    }
+
+    #[test]
+    fn unit_quaternion_try_from() {
+        let unit = Quaternion::new(array![0.5, 0.5, 0.5, 0.5]);
+        assert!(UnitQuaternion::try_from(unit).is_ok());
+
+        // The obvious, intended way to build a UnitQuaternion: normalize
+        // first. This must succeed even though normalize() doesn't land
+        // exactly on sum-of-squares == 1.0 in f32.
+        let normalized = Quaternion::new(array![1.0, 0.5, 0.5, 0.5]).normalized();
+        assert!(UnitQuaternion::try_from(normalized).is_ok());
+
+        let not_unit = Quaternion::new(array![1.0, 0.5, 0.5, 0.5]);
+        assert!(UnitQuaternion::try_from(not_unit).is_err());
+    }
+
+    #[test]
+    fn unit_quaternion_inverse_is_conjugate() {
+        let q = Quaternion::new(array![0.5, 0.5, 0.5, 0.5]);
+        let uq = UnitQuaternion::new_normalize(q.clone());
+        let inv: Quaternion = uq.inverse().into();
+        assert_eq!(inv.q, q.conjugate().q);
+    }
+
+    #[test]
+    fn from_euler_round_trip_taitbryan() {
+        let (yaw, pitch, roll) = (0.3, 0.2, 0.1);
+        let q = Quaternion::from_euler(yaw, pitch, roll);
+        let tb = q.taitbryan();
+        assert!((tb[0] - yaw).abs() < 0.0001);
+        assert!((tb[1] - pitch).abs() < 0.0001);
+        assert!((tb[2] - roll).abs() < 0.0001);
+    }
+
+    #[test]
+    fn exp_ln_round_trip() {
+        let q = Quaternion::new(array![0.1, 0.2, 0.3, 0.4]);
+        let round_tripped = q.ln().exp();
+        for i in 0..4 {
+            assert!((round_tripped.q[i] - q.q[i]).abs() < 0.0001);
+        }
+    }
+
+    #[test]
+    fn exp_pure_scalar() {
+        let q = Quaternion::from_wxyz(1.0, 0.0, 0.0, 0.0);
+        let e = q.exp();
+        assert!((e.q[0] - f32::consts::E).abs() < 0.0001);
+        assert_eq!(e.q[1], 0.0);
+        assert_eq!(e.q[2], 0.0);
+        assert_eq!(e.q[3], 0.0);
+    }
+
+    #[test]
+    fn powf_one_is_identity() {
+        let q = Quaternion::new(array![0.5, 0.5, 0.5, 0.5]);
+        let p = q.powf(1.0);
+        for i in 0..4 {
+            assert!((p.q[i] - q.q[i]).abs() < 0.0001);
+        }
+    }
+
+    #[test]
+    fn rotation_matrix_identity() {
+        let q = Quaternion::from_wxyz(1.0, 0.0, 0.0, 0.0);
+        assert_eq!(q.to_rotation_matrix(), Array2::eye(3));
+        assert_eq!(q.to_homogeneous(), Array2::eye(4));
+    }
+
+    #[test]
+    fn rotation_matrix_round_trip() {
+        let q = Quaternion::new(array![0.5, 0.5, 0.5, 0.5]);
+        let m = q.to_rotation_matrix();
+        let q2 = Quaternion::from_rotation_matrix(&m);
+        // q and -q represent the same rotation.
+        let same_sign = (q.q[0] - q2.q[0]).abs() < 0.0001;
+        let expected = if same_sign { q.q.clone() } else { -q.q.clone() };
+        for i in 0..4 {
+            assert!((expected[i] - q2.q[i]).abs() < 0.0001);
+        }
+    }
+
+    #[test]
+    fn hamilton_product_batch_matches_single() {
+        let q1 = Quaternion::new(array![-0.754, -0.18, -0.327, 0.54]);
+        let q2 = Quaternion::new(array![0.5, 0.5, 0.5, 0.5]);
+        let expected = (q1.clone() * q2.clone()).to_array();
+
+        let a = array![[-0.754, -0.18, -0.327, 0.54]];
+        let b = array![[0.5, 0.5, 0.5, 0.5]];
+        let result = hamilton_product_batch(&a.view(), &b.view());
+
+        for i in 0..4 {
+            assert!((result[[0, i]] - expected[i]).abs() < 0.0001);
+        }
+    }
+
+    #[test]
+    fn rotate_vectors_matches_single() {
+        let v = array![-7.135, -0.297, 6.37];
+        let q = Quaternion::new(array![-0.754, -0.18, -0.327, 0.54]);
+        let expected = q.rotate_vector(&v);
+
+        let quats = array![[-0.754, -0.18, -0.327, 0.54]];
+        let vecs = array![[-7.135, -0.297, 6.37]];
+        let result = rotate_vectors(&quats.view(), &vecs.view());
+
+        for i in 0..3 {
+            assert!((result[[0, i]] - expected[i]).abs() < 0.0001);
+        }
+    }
+
+    #[test]
+    fn nlerp_endpoints() {
+        let q1 = Quaternion::from_wxyz(1.0, 0.0, 0.0, 0.0);
+        let q2 = Quaternion::new(array![0.5, 0.5, 0.5, 0.5]);
+        assert_eq!(q1.nlerp(&q2, 0.0).q, q1.q);
+        assert_eq!(q1.nlerp(&q2, 1.0).q, q2.q);
+    }
 }